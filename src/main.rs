@@ -1,10 +1,12 @@
 mod synthesizer;
 mod tetris;
 
-use std::time::{Duration, Instant};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
+use crate::tetris::config::Config;
+use crate::tetris::game_mode::GameModeKind;
 use crate::tetris::render_engine::RenderEngine;
-use crate::tetris::{GameAction, InputEvent, Tetris};
+use crate::tetris::{GameAction, InputEvent, OutputEvent, Tetris};
 
 use crate::tetris::render_engine::sdl::SDL2RenderEngine;
 use sdl2::event::Event;
@@ -13,9 +15,11 @@ use sdl2::keyboard::Keycode;
 fn main() {
     let sdl_context = sdl2::init().unwrap();
 
-    let render_engine = SDL2RenderEngine::new(&sdl_context);
+    let (config, seed) = parse_args();
+    println!("Seed: {seed}");
+    let tetris = Tetris::new(config, seed);
 
-    let tetris = Tetris::new(0);
+    let render_engine = SDL2RenderEngine::new(&sdl_context, tetris.get_play_field_size());
 
     let event_pump = sdl_context.event_pump().unwrap();
 
@@ -29,14 +33,97 @@ fn main() {
     audio_device.pause();
 }
 
-//TODO: multiple simultaneous key presses don't seem to work
+// e.g. `tetris --ruleset guideline --randomizer bag --seed 1234`, the ruleset
+// should come first as it replaces any options given before it
+fn parse_args() -> (Config, u64) {
+    let mut config = Config::default();
+    let mut seed = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--ruleset", Some(name)) => {
+                config = match name.as_str() {
+                    "gameboy" => Config::default(),
+                    "guideline" => Config::guideline(),
+                    "nes" => Config::nes(),
+                    _ => panic!("unknown ruleset: {name}"),
+                };
+            }
+            ("--randomizer", Some(name)) => {
+                config.randomizer = name.parse().unwrap();
+            }
+            ("--level", Some(value)) => {
+                config.start_level = value.parse().unwrap();
+            }
+            ("--level-progression", Some(name)) => {
+                config.level_progression = name.parse().unwrap();
+            }
+            //20, 40 and 100 lines are the usual sprints
+            ("--sprint", Some(value)) => {
+                config.mode = GameModeKind::Sprint {
+                    line_goal: value.parse().unwrap(),
+                };
+            }
+            //in minutes, usually 2 or 3
+            ("--ultra", Some(value)) => {
+                let minutes: u64 = value.parse().unwrap();
+                //the game runs at 60 frames a second
+                config.mode = GameModeKind::Ultra {
+                    frame_limit: minutes * 60 * 60,
+                };
+            }
+            //high goes from 0 to 5, anything above that is played as 5
+            ("--btype", Some(value)) => {
+                config.mode = GameModeKind::BType {
+                    high: value.parse().unwrap(),
+                };
+            }
+            ("--width", Some(value)) => {
+                config.play_field_width = value.parse().unwrap();
+            }
+            ("--height", Some(value)) => {
+                config.play_field_height = value.parse().unwrap();
+            }
+            ("--seed", Some(value)) => {
+                seed = Some(value.parse().unwrap());
+            }
+            _ => panic!("unrecognised argument: {arg}"),
+        }
+    }
+
+    //pick a fresh seed each game unless one was asked for
+    let seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64
+    });
+
+    (config, seed)
+}
+
+fn get_key_binding(keycode: Keycode) -> Option<GameAction> {
+    match keycode {
+        Keycode::Up | Keycode::X => Some(GameAction::Rotate),
+        Keycode::Z | Keycode::LCtrl => Some(GameAction::RotateCcw),
+        Keycode::A => Some(GameAction::Rotate180),
+        Keycode::Left => Some(GameAction::MoveLeft),
+        Keycode::Right => Some(GameAction::MoveRight),
+        Keycode::Down => Some(GameAction::MoveDown),
+        Keycode::Space => Some(GameAction::HardDrop),
+        Keycode::C | Keycode::LShift => Some(GameAction::Hold),
+        _ => None,
+    }
+}
+
 fn game_loop(
     mut tetris: Tetris,
     mut render_engine: impl RenderEngine,
     mut event_pump: sdl2::EventPump,
 ) {
     let mut loop_start = Instant::now();
-    let frame_time = Duration::from_secs_f64(1.0 / 60.0);
+    let frame_time = tetris::FRAME_DURATION;
 
     loop {
         let delta_time = loop_start.elapsed();
@@ -45,33 +132,68 @@ fn game_loop(
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. } => {
+                    println!("Window closed, exiting game loop");
                     tetris.recieve_event(InputEvent::Quit);
                 }
                 Event::KeyDown {
-                    keycode: Some(keycode),
+                    keycode: Some(Keycode::Escape | Keycode::P),
+                    repeat: false,
                     ..
-                } => match keycode {
-                    Keycode::Up => {
-                        tetris.recieve_event(InputEvent::TakeAction(GameAction::Rotate));
-                    }
-                    Keycode::Left => {
-                        tetris.recieve_event(InputEvent::TakeAction(GameAction::MoveLeft));
+                } => {
+                    if tetris.is_paused() {
+                        tetris.recieve_event(InputEvent::Resume);
+                    } else {
+                        tetris.recieve_event(InputEvent::Pause);
                     }
-                    Keycode::Right => {
-                        tetris.recieve_event(InputEvent::TakeAction(GameAction::MoveRight));
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(action) = get_key_binding(keycode) {
+                        tetris.recieve_event(InputEvent::Press(action));
                     }
-                    Keycode::Down => {
-                        tetris.recieve_event(InputEvent::TakeAction(GameAction::MoveDown));
+                }
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some(action) = get_key_binding(keycode) {
+                        tetris.recieve_event(InputEvent::Release(action));
                     }
-                    _ => {}
-                },
+                }
                 _ => {}
             }
         }
 
-        let output_event = tetris.game_tick(delta_time);
+        let mut should_exit = false;
+        for output_event in tetris.game_tick(delta_time) {
+            match output_event {
+                OutputEvent::Exit => should_exit = true,
+                //the final board stays on screen until the window is closed
+                OutputEvent::TopOut { level, reason, .. } => {
+                    let statistics = tetris.get_statistics();
+                    println!("Game over: {reason:?}");
+                    println!("Level: {level}");
+                    println!("Lines: {}", statistics.lines_cleared);
+                    println!("Score: {}", statistics.score);
+                }
+                OutputEvent::Completed(statistics) => {
+                    println!("Complete!");
+                    println!("Lines: {}", statistics.lines_cleared);
+                    println!("Score: {}", statistics.score);
+                    println!("Time: {:.2}s", statistics.time().as_secs_f64());
+                    println!("Pieces: {}", statistics.pieces_placed);
+                    println!("Pieces per second: {:.2}", statistics.pieces_per_second());
+                }
+                _ => {}
+            }
+
+            render_engine.handle_event(&output_event);
+        }
 
-        if let tetris::OutputEvent::Exit = output_event {
+        if should_exit {
             break;
         }
 
@@ -1,69 +1,156 @@
 use std::{
-    collections::HashSet,
-    time::{Duration, Instant},
+    collections::{HashSet, VecDeque},
+    time::Duration,
 };
 
-use ndarray::{Array2, Axis};
-
-use crate::tetris::tetromino::Tetromino;
-
+use ndarray::{Array2, ArrayView2, Axis, s};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::tetris::config::{Config, EntryDelay};
+use crate::tetris::game_mode::GameMode;
+use crate::tetris::input::InputState;
+use crate::tetris::randomizer::Randomizer;
+use crate::tetris::tetromino::{Position, RotationDirection, Tetromino, TetrominoType};
+
+pub mod config;
+pub mod game_mode;
+pub mod input;
+pub mod level_progression;
+pub mod randomizer;
 pub mod render_engine;
 pub mod tetromino;
 
-pub const PLAY_FIELD_WIDTH: usize = 10;
-pub const PLAY_FIELD_HEIGHT: usize = 20;
-
 const fn from_frames(frames: u64) -> Duration {
     Duration::from_nanos(16666666 * frames)
 }
 
+// the game always advances in whole 60hz frames no matter how often it's ticked
+pub const FRAME_DURATION: Duration = from_frames(1);
+
 const MAX_LEVEL: usize = 21;
 
-const LEVEL_DROP_DURATIONS: [Duration; MAX_LEVEL] = [
-    from_frames(53),
-    from_frames(49),
-    from_frames(45),
-    from_frames(41),
-    from_frames(37),
-    from_frames(33),
-    from_frames(28),
-    from_frames(22),
-    from_frames(17),
-    from_frames(11),
-    from_frames(10),
-    from_frames(9),
-    from_frames(8),
-    from_frames(7),
-    from_frames(6),
-    from_frames(6),
-    from_frames(5),
-    from_frames(5),
-    from_frames(4),
-    from_frames(4),
-    from_frames(3),
+// frames per row the piece falls under gravity at each level
+const LEVEL_DROP_FRAMES: [u64; MAX_LEVEL] = [
+    53, 49, 45, 41, 37, 33, 28, 22, 17, 11, 10, 9, 8, 7, 6, 6, 5, 5, 4, 4, 3,
 ];
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum GameAction {
     Rotate,
+    RotateCcw,
+    Rotate180,
     MoveDown,
     MoveRight,
     MoveLeft,
+    HardDrop,
+    Hold,
 }
 
 #[derive(Debug)]
 pub enum InputEvent {
     Quit,
-    TakeAction(GameAction),
+    Pause,
+    Resume,
+    //a button being pressed takes its action straight away, holding left, right or down
+    //then repeats it until it's released
+    Press(GameAction),
+    Release(GameAction),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+// what kind of clear a locked piece made
+#[derive(Debug, Clone, Copy)]
+pub struct LineClear {
+    pub lines: usize,
+    pub t_spin: TSpin,
+    //how many placements in a row have cleared lines, including this one
+    pub combo: u32,
+    //whether this was a difficult clear straight after another
+    pub back_to_back: bool,
+}
+
+// everything notable that happens in the game is reported as one of these so renderers,
+// audio and anything else can follow along
+#[derive(Debug, Clone)]
 pub enum OutputEvent {
-    NoOp,
     Exit,
+    PieceLocked(Tetromino),
+    //a T-spin that cleared nothing is still reported, with no lines
+    LinesCleared { kind: LineClear },
+    LevelUp(u32),
+    Hold,
+    TopOut { level: u32, reason: TopOutReason },
+    //the mode's goal was reached
+    Completed(Statistics),
+    ScoreChanged(u32),
+}
+
+// the ways a game can end, named as in the guideline
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopOutReason {
+    //a new piece spawned overlapping the stack
+    BlockOut,
+    //a piece locked entirely above the play field
+    LockOut,
+    //a piece locked with some of its cells above the play field
+    PartialLockOut,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Playing,
+    //the board and score are left as they were when the game ended
+    GameOver(TopOutReason),
+    Completed,
 }
 
-#[derive(Default, Clone)]
+// how the game has gone so far
+#[derive(Debug, Clone, Copy)]
+pub struct Statistics {
+    pub frames: u64,
+    pub pieces_placed: u32,
+    pub lines_cleared: u32,
+    pub score: u32,
+}
+
+impl Statistics {
+    pub fn time(&self) -> Duration {
+        from_frames(self.frames)
+    }
+
+    pub fn pieces_per_second(&self) -> f64 {
+        if self.frames == 0 {
+            return 0.0;
+        }
+        self.pieces_placed as f64 / self.time().as_secs_f64()
+    }
+}
+
+// what the game is doing between one piece locking and the next one appearing
+enum Phase {
+    //a piece is in play
+    Falling,
+    //cleared rows stay on the board for a while before the rows above fall into place
+    LineClear {
+        rows: Vec<usize>,
+        frames_left: u64,
+        entry_delay: u64,
+    },
+    //waiting for the next piece to spawn
+    Entry {
+        frames_left: u64,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
 pub enum Cell {
     #[default]
     Empty,
@@ -80,58 +167,454 @@ impl Cell {
 }
 
 pub struct Tetris {
-    level: u32,
+    //decides the level and when the game is won
+    mode: Box<dyn GameMode>,
     score: u32,
-    lines_to_clear_before_next_level: u32,
+    pieces_placed: u32,
+    lines_cleared: u32,
     play_field: ndarray::Array2<Cell>,
+    //the size of the visible play field, the vanish zone sits on top of it
+    play_field_width: usize,
+    play_field_height: usize,
+    //where new pieces enter, centred just above the visible play field
+    spawn_position: Position,
     current_tetromino: Tetromino,
+    next_tetrominoes: VecDeque<TetrominoType>,
+    randomizer: Box<dyn Randomizer>,
+    //every random decision in the game comes from here so a seed reproduces the whole game
+    rng: ChaCha8Rng,
+    state: GameState,
+    phase: Phase,
+    entry_delay: EntryDelay,
+    line_clear_delay: u64,
+    //while paused no time passes in the game at all
+    paused: bool,
     should_exit: bool,
     action_queue: Vec<GameAction>,
-    time_of_last_move: Instant,
+    //events that have happened since the game was last ticked
+    output_events: Vec<OutputEvent>,
+    //frames simulated so far
+    frame_count: u64,
+    frames_since_last_drop: u64,
+    //time handed to game_tick that hasn't yet made up a whole frame
+    unsimulated_time: Duration,
+    soft_drop_factor: u32,
+    input: InputState,
+    held_tetromino: Option<TetrominoType>,
+    //only one hold is allowed per piece placed
+    can_hold: bool,
+    lock_delay: u64,
+    max_lock_resets: u32,
+    //how long the current piece has been on the ground
+    lock_delay_frames: u64,
+    lock_resets: u32,
+    //reaching a new lowest row gives the piece back all its lock resets
+    lowest_row: i32,
+    //which wall kick the last successful action used, if it was a rotation
+    last_rotation_kick: Option<Position>,
+    combo: u32,
+    back_to_back: u32,
+    //whether a piece only partly above the play field when it locks ends the game
+    partial_lock_out: bool,
 }
 
 impl Tetris {
-    pub fn new(level: u32) -> Self {
-        let play_field: Array2<Cell> =
-            ndarray::Array2::<Cell>::from_elem((PLAY_FIELD_WIDTH, PLAY_FIELD_HEIGHT), Cell::Empty);
+    pub fn new(config: Config, seed: u64) -> Self {
+        let mode = config
+            .mode
+            .create(config.start_level, config.level_progression);
+        Self::with_mode(config, seed, mode)
+    }
 
-        Tetris {
-            level,
+    // plays a game mode that isn't one of the built in kinds, the mode in the config is
+    // ignored
+    pub fn with_mode(config: Config, seed: u64, mode: Box<dyn GameMode>) -> Self {
+        //the rows above the visible play field are a hidden buffer pieces can spawn, rotate
+        //and lock in
+        let play_field: Array2<Cell> = ndarray::Array2::<Cell>::from_elem(
+            (
+                config.play_field_width,
+                config.play_field_height + config.vanish_zone_height,
+            ),
+            Cell::Empty,
+        );
+        //pieces reach one column left and two right of where they spawn, so on narrow play
+        //fields they have to be nudged left to fit
+        assert!(
+            config.play_field_width >= 4,
+            "the play field has to be at least 4 wide to fit every piece"
+        );
+        let spawn_column = (config.play_field_width / 2).min(config.play_field_width - 3);
+        let spawn_position = (spawn_column as i32, config.play_field_height as i32);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut randomizer = config.randomizer.create();
+        let current_tetromino = Tetromino::new(randomizer.next(&mut rng), spawn_position);
+        let lowest_row = current_tetromino.position.1;
+        let next_tetrominoes = (0..config.preview_length)
+            .map(|_| randomizer.next(&mut rng))
+            .collect();
+
+        let mut tetris = Tetris {
+            mode,
             score: 0,
-            lines_to_clear_before_next_level: (level * 10) + 10, //based on gameboy marathon mode
+            pieces_placed: 0,
+            lines_cleared: 0,
             play_field,
-            current_tetromino: Tetromino::random(),
+            play_field_width: config.play_field_width,
+            play_field_height: config.play_field_height,
+            spawn_position,
+            current_tetromino,
+            next_tetrominoes,
+            randomizer,
+            rng,
+            state: GameState::Playing,
+            phase: Phase::Falling,
+            entry_delay: config.entry_delay,
+            line_clear_delay: config.line_clear_delay,
+            paused: false,
             should_exit: false,
             action_queue: Vec::<GameAction>::new(),
-            time_of_last_move: Instant::now(),
-        }
+            output_events: Vec::new(),
+            frame_count: 0,
+            frames_since_last_drop: 0,
+            unsimulated_time: Duration::ZERO,
+            soft_drop_factor: config.soft_drop_factor.max(1),
+            input: InputState::new(config.das, config.arr),
+            held_tetromino: None,
+            can_hold: true,
+            lock_delay: config.lock_delay,
+            max_lock_resets: config.max_lock_resets,
+            lock_delay_frames: 0,
+            lock_resets: 0,
+            lowest_row,
+            last_rotation_kick: None,
+            combo: 0,
+            back_to_back: 0,
+            partial_lock_out: config.partial_lock_out,
+        };
+
+        let visible_field = tetris
+            .play_field
+            .slice_mut(s![.., ..config.play_field_height]);
+        tetris.mode.start(visible_field, &mut tetris.rng);
+
+        tetris
     }
 
     pub fn recieve_event(&mut self, input_event: InputEvent) {
         match input_event {
             InputEvent::Quit => {
-                println!("Window closed, exiting game loop");
                 self.should_exit = true;
             }
-            InputEvent::TakeAction(action) => {
-                self.action_queue.push(action);
+            //there's nothing to pause once the game is over
+            InputEvent::Pause => {
+                self.paused = self.state == GameState::Playing;
+            }
+            InputEvent::Resume => {
+                self.paused = false;
+            }
+            //presses while paused are ignored, releases still count so keys let go of during
+            //the pause don't stay held
+            InputEvent::Press(action) => {
+                if !self.paused {
+                    self.input.press(action);
+                    self.action_queue.push(action);
+                }
+            }
+            InputEvent::Release(action) => {
+                self.input.release(action);
+            }
+        }
+    }
+
+    pub fn get_level(&self) -> u32 {
+        self.mode.level()
+    }
+
+    pub fn get_state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn get_lines_remaining(&self) -> Option<u32> {
+        self.mode.lines_remaining()
+    }
+
+    pub fn get_countdown(&self) -> Option<(Duration, f32)> {
+        self.mode.countdown()
+    }
+
+    pub fn get_statistics(&self) -> Statistics {
+        Statistics {
+            frames: self.frame_count,
+            pieces_placed: self.pieces_placed,
+            lines_cleared: self.lines_cleared,
+            score: self.score,
+        }
+    }
+
+    // while paused nothing on the board is handed out, so renderers can't show it
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // the visible part of the play field, indexed [[x, y]] with y = 0 at the bottom
+    pub fn get_play_field(&self) -> Option<ArrayView2<'_, Cell>> {
+        if self.paused {
+            return None;
+        }
+        Some(self.play_field.slice(s![.., ..self.play_field_height]))
+    }
+
+    // the width and height of the visible play field
+    pub fn get_play_field_size(&self) -> (usize, usize) {
+        (self.play_field_width, self.play_field_height)
+    }
+
+    // there's no piece in play during the line clear and entry delays
+    pub fn get_current_tetromino(&self) -> Option<&Tetromino> {
+        (self.is_falling() && !self.paused).then_some(&self.current_tetromino)
+    }
+
+    // the rows being cleared and how far through the line clear delay the game is, from 0
+    // to 1, so renderers can animate them
+    pub fn get_line_clear(&self) -> Option<(&[usize], f32)> {
+        if self.paused {
+            return None;
+        }
+        match &self.phase {
+            Phase::LineClear {
+                rows, frames_left, ..
+            } => {
+                let progress = 1.0 - *frames_left as f32 / self.line_clear_delay as f32;
+                Some((rows, progress))
+            }
+            _ => None,
+        }
+    }
+
+    // how many placements in a row have cleared lines
+    pub fn get_combo(&self) -> u32 {
+        self.combo
+    }
+
+    // how many difficult clears (Tetrises and T-spins) there have been without an easier
+    // line clear in between
+    pub fn get_back_to_back(&self) -> u32 {
+        self.back_to_back
+    }
+
+    // where the current tetromino would land if it was dropped straight down
+    pub fn ghost_position(&self) -> Option<Position> {
+        let (x, y) = self.get_current_tetromino()?.position;
+        Some((x, y - self.drop_distance()))
+    }
+
+    // the upcoming pieces, in the order they will be played
+    pub fn get_next_tetrominoes(&self) -> impl Iterator<Item = TetrominoType> + '_ {
+        let shown = if self.paused {
+            0
+        } else {
+            self.next_tetrominoes.len()
+        };
+        self.next_tetrominoes.iter().copied().take(shown)
+    }
+
+    pub fn get_held_tetromino(&self) -> Option<TetrominoType> {
+        self.held_tetromino.filter(|_| !self.paused)
+    }
+
+    fn is_falling(&self) -> bool {
+        self.state == GameState::Playing && matches!(self.phase, Phase::Falling)
+    }
+
+    // the visible rows plus the vanish zone above them
+    fn total_height(&self) -> usize {
+        self.play_field.dim().1
+    }
+
+    // the walls and floor count as blocked, the space above the vanish zone doesn't
+    fn is_blocked(&self, (x, y): Position) -> bool {
+        if x < 0 || x >= self.play_field_width as i32 {
+            return true;
+        }
+
+        if y < 0 {
+            return true;
+        }
+
+        y < self.total_height() as i32 && self.play_field[[x as usize, y as usize]].is_block()
+    }
+
+    fn is_valid_position(&self, tetromino: &Tetromino) -> bool {
+        let (x_origin, y_origin) = tetromino.position;
+        tetromino
+            .get_positions()
+            .iter()
+            .all(|(x_offset, y_offset)| {
+                !self.is_blocked((x_origin + x_offset, y_origin + y_offset))
+            })
+    }
+
+    // how many rows the current tetromino can fall before it lands on something
+    fn drop_distance(&self) -> i32 {
+        let mut dropped = self.current_tetromino.clone();
+        let mut distance = 0;
+        loop {
+            let (x, y) = dropped.position;
+            dropped.position = (x, y - 1);
+            if !self.is_valid_position(&dropped) {
+                return distance;
             }
+            distance += 1;
         }
     }
 
-    pub fn get_current_tetromino(&self) -> &Tetromino {
-        &self.current_tetromino
+    fn next_tetromino(&mut self) -> Tetromino {
+        let next_type = self.randomizer.next(&mut self.rng);
+        self.next_tetrominoes.push_back(next_type);
+        Tetromino::new(
+            self.next_tetrominoes.pop_front().unwrap(),
+            self.spawn_position,
+        )
     }
 
-    fn take_action(&mut self, action: GameAction) -> OutputEvent {
+    fn spawn_tetromino(&mut self, tetromino: Tetromino) {
+        self.current_tetromino = tetromino;
+        self.reset_lock_delay();
+        //a new piece can't have T-spun using the last one's rotation
+        self.last_rotation_kick = None;
+        //or start partway through the last one's gravity
+        self.frames_since_last_drop = 0;
+
+        if !self.is_valid_position(&self.current_tetromino) {
+            self.game_over(TopOutReason::BlockOut);
+        }
+    }
+
+    fn complete(&mut self) {
+        self.state = GameState::Completed;
+        self.output_events
+            .push(OutputEvent::Completed(self.get_statistics()));
+    }
+
+    fn game_over(&mut self, reason: TopOutReason) {
+        self.state = GameState::GameOver(reason);
+        self.output_events.push(OutputEvent::TopOut {
+            level: self.get_level(),
+            reason,
+        });
+    }
+
+    fn add_score(&mut self, points: u32) {
+        if points > 0 {
+            self.score += points;
+            self.output_events
+                .push(OutputEvent::ScoreChanged(self.score));
+        }
+    }
+
+    fn hold_current_tetromino(&mut self) {
+        if !self.can_hold {
+            return;
+        }
+
+        let current_type = self.current_tetromino.tetromino_type;
+        let tetromino = match self.held_tetromino {
+            Some(held_type) => Tetromino::new(held_type, self.spawn_position),
+            None => self.next_tetromino(),
+        };
+        self.held_tetromino = Some(current_type);
+        self.can_hold = false;
+        self.output_events.push(OutputEvent::Hold);
+        self.spawn_tetromino(tetromino);
+    }
+
+    // called whenever a new piece enters play
+    fn reset_lock_delay(&mut self) {
+        self.lock_delay_frames = 0;
+        self.lock_resets = 0;
+        self.lowest_row = self.current_tetromino.position.1;
+    }
+
+    // moving or rotating a piece on the ground buys it more time, up to a limit
+    fn extend_lock_delay(&mut self) {
+        if self.lock_delay_frames > 0 && self.lock_resets < self.max_lock_resets {
+            self.lock_delay_frames = 0;
+            self.lock_resets += 1;
+        }
+    }
+
+    // returns the wall kick that was used if the rotation succeeded
+    fn rotate_current_tetromino(&mut self, direction: RotationDirection) -> Option<Position> {
+        let wall_kicks = self.current_tetromino.get_wall_kicks(direction);
+        self.current_tetromino.rotate(direction);
+
+        //try each kick in turn, keeping the first one that fits
+        let (x, y) = self.current_tetromino.position;
+        for (x_kick, y_kick) in wall_kicks {
+            self.current_tetromino.position = (x + x_kick, y + y_kick);
+            if self.is_valid_position(&self.current_tetromino) {
+                return Some((*x_kick, *y_kick));
+            }
+        }
+        None
+    }
+
+    // a T piece that was rotated into place with three of the four corners around its centre
+    // blocked is a T-spin, it's only a mini T-spin if one of the two corners it points at is
+    // open, unless it got there with one of the long kicks that move it two rows
+    fn detect_t_spin(&self) -> TSpin {
+        let Some((x_kick, y_kick)) = self.last_rotation_kick else {
+            return TSpin::None;
+        };
+        if self.current_tetromino.tetromino_type != TetrominoType::T {
+            return TSpin::None;
+        }
+
+        let (x, y) = self.current_tetromino.position;
+        let is_corner_blocked =
+            |(x_offset, y_offset): Position| self.is_blocked((x + x_offset, y + y_offset));
+
+        let blocked_corners = [(-1, 1), (1, 1), (1, -1), (-1, -1)]
+            .into_iter()
+            .filter(|corner| is_corner_blocked(*corner))
+            .count();
+        if blocked_corners < 3 {
+            return TSpin::None;
+        }
+
+        let front_corners = match self.current_tetromino.get_rotation() {
+            0 => [(-1, 1), (1, 1)],
+            1 => [(1, 1), (1, -1)],
+            2 => [(1, -1), (-1, -1)],
+            _ => [(-1, -1), (-1, 1)],
+        };
+        let long_kick = x_kick.abs() == 1 && y_kick.abs() == 2;
+
+        if front_corners.into_iter().all(is_corner_blocked) || long_kick {
+            TSpin::Full
+        } else {
+            TSpin::Mini
+        }
+    }
+
+    fn take_action(&mut self, action: GameAction) {
         let tetronimo_backup = self.current_tetromino.clone();
+        let mut rotation_kick = None;
         match action {
             GameAction::Rotate => {
-                self.current_tetromino.rotate();
+                rotation_kick = self.rotate_current_tetromino(RotationDirection::Clockwise);
+            }
+            GameAction::RotateCcw => {
+                rotation_kick = self.rotate_current_tetromino(RotationDirection::CounterClockwise);
+            }
+            GameAction::Rotate180 => {
+                rotation_kick = self.rotate_current_tetromino(RotationDirection::HalfTurn);
             }
             GameAction::MoveDown => {
-                let (x, y) = self.current_tetromino.position;
-                self.current_tetromino.position = (x, y - 1);
+                self.move_current_tetromino_down(true);
+                return;
             }
             GameAction::MoveRight => {
                 let (x, y) = self.current_tetromino.position;
@@ -141,71 +624,144 @@ impl Tetris {
                 let (x, y) = self.current_tetromino.position;
                 self.current_tetromino.position = (x - 1, y);
             }
-        }
-
-        let mut legal_move = true;
-        for (x_offset, y_offset) in self.current_tetromino.get_positions() {
-            let (x_origin, y_origin) = self.current_tetromino.position;
-            let (x, y) = (x_origin + x_offset, y_origin + y_offset);
-
-            if x < 0 || x >= PLAY_FIELD_WIDTH as i32 {
-                legal_move = false;
-                break;
-            }
+            GameAction::HardDrop => {
+                let distance = self.drop_distance();
+                let (x, y) = self.current_tetromino.position;
+                self.current_tetromino.position = (x, y - distance);
+                if distance > 0 {
+                    self.last_rotation_kick = None;
+                }
 
-            if y < 0 {
-                legal_move = false;
-                break;
+                // 2 points per row, same as the modern guideline
+                self.add_score(2 * distance as u32);
+                self.commit_current_tetromino();
+                return;
             }
-
-            if y < PLAY_FIELD_HEIGHT as i32 && self.play_field[[x as usize, y as usize]].is_block()
-            {
-                legal_move = false;
-                break;
+            GameAction::Hold => {
+                self.hold_current_tetromino();
+                return;
             }
         }
 
         //if the move isn't legal undo it
-        if !legal_move {
+        if !self.is_valid_position(&self.current_tetromino) {
             self.current_tetromino = tetronimo_backup;
+        } else {
+            self.last_rotation_kick = rotation_kick;
+            self.extend_lock_delay();
+        }
+    }
 
-            //if the game or the player was trying to move the piece down and couldn't
-            //commit it to the board
-            match action {
-                GameAction::MoveDown => {
-                    return self.commit_current_tetromino();
-                }
-                _ => {
-                    return OutputEvent::NoOp;
-                }
+    // soft drops are moves the player asked for and score a point per row,
+    // drops caused by gravity alone don't
+    fn move_current_tetromino_down(&mut self, soft_drop: bool) {
+        let (x, y) = self.current_tetromino.position;
+        self.current_tetromino.position = (x, y - 1);
+
+        //if the piece couldn't move down commit it to the board, unless it gets a lock delay
+        if !self.is_valid_position(&self.current_tetromino) {
+            self.current_tetromino.position = (x, y);
+            if self.lock_delay == 0 {
+                self.commit_current_tetromino();
             }
+            return;
+        }
+
+        self.last_rotation_kick = None;
+
+        if y - 1 < self.lowest_row {
+            self.lowest_row = y - 1;
+            self.lock_delay_frames = 0;
+            self.lock_resets = 0;
+        }
+
+        if soft_drop {
+            self.add_score(1);
         }
-        OutputEvent::NoOp
     }
 
-    fn commit_current_tetromino(&mut self) -> OutputEvent {
+    fn commit_current_tetromino(&mut self) {
+        //has to be checked before the piece becomes part of the stack
+        let t_spin = self.detect_t_spin();
+
+        //cells above the vanish zone have nowhere to go so they're lost
+        let mut cells_above = 0;
         for (x_offset, y_offset) in self.current_tetromino.get_positions() {
             let (x_origin, y_origin) = self.current_tetromino.position;
             let (x, y) = (x_origin + x_offset, y_origin + y_offset);
 
-            if y < PLAY_FIELD_HEIGHT as i32 {
+            if y < self.total_height() as i32 {
                 self.play_field[[x as usize, y as usize]] =
                     Cell::Block(self.current_tetromino.tetromino_type);
-            } else {
-                //game over
-                println!("Level: {}", self.level);
-                println!("Score: {}", self.score);
-                return OutputEvent::Exit;
             }
+            if y >= self.play_field_height as i32 {
+                cells_above += 1;
+            }
+        }
+        self.pieces_placed += 1;
+        self.output_events
+            .push(OutputEvent::PieceLocked(self.current_tetromino.clone()));
+        self.mode.piece_locked(&self.current_tetromino);
+
+        if cells_above == self.current_tetromino.get_positions().len() {
+            self.game_over(TopOutReason::LockOut);
+            return;
+        }
+        if cells_above > 0 && self.partial_lock_out {
+            self.game_over(TopOutReason::PartialLockOut);
+            return;
+        }
+
+        let lowest_row = self
+            .current_tetromino
+            .get_positions()
+            .iter()
+            .map(|(_, y_offset)| self.current_tetromino.position.1 + y_offset)
+            .min()
+            .unwrap();
+        let entry_delay = self.entry_delay.frames(lowest_row);
+
+        let rows = self.clear_lines(t_spin);
+        if self.mode.is_complete() {
+            self.collapse_rows(&rows);
+            self.add_score(self.mode.completion_bonus());
+            self.complete();
+            return;
         }
 
-        self.current_tetromino = Tetromino::random();
+        if !rows.is_empty() && self.line_clear_delay > 0 {
+            self.phase = Phase::LineClear {
+                rows,
+                frames_left: self.line_clear_delay,
+                entry_delay,
+            };
+        } else {
+            //lines are collapsed first so the next piece has room to spawn
+            self.collapse_rows(&rows);
+            self.start_entry_delay(entry_delay);
+        }
+    }
 
-        self.clear_lines();
-        OutputEvent::NoOp
+    fn start_entry_delay(&mut self, frames: u64) {
+        if frames == 0 {
+            self.spawn_next_tetromino();
+        } else {
+            self.phase = Phase::Entry {
+                frames_left: frames,
+            };
+        }
     }
 
-    fn clear_lines(&mut self) {
+    fn spawn_next_tetromino(&mut self) {
+        self.phase = Phase::Falling;
+        let tetromino = self.next_tetromino();
+        self.spawn_tetromino(tetromino);
+        self.can_hold = true;
+    }
+
+    // scores any lines the last piece completed and returns their rows, they stay on the
+    // board until they're collapsed
+    fn clear_lines(&mut self, t_spin: TSpin) -> Vec<usize> {
         let mut cleared_lines: HashSet<usize> = HashSet::<usize>::new();
 
         for (row_index, row) in self.play_field.lanes(Axis(0)).into_iter().enumerate() {
@@ -216,67 +772,242 @@ impl Tetris {
             }
         }
 
-        if cleared_lines.len() == 4 {
-            println!("Tetris!");
+        //Tetrises and T-spins that clear lines are difficult clears, one straight after another
+        //is a back to back, any other line clear breaks the chain
+        let mut back_to_back = false;
+        if !cleared_lines.is_empty() {
+            if cleared_lines.len() == 4 || t_spin != TSpin::None {
+                back_to_back = self.back_to_back > 0;
+                self.back_to_back += 1;
+            } else {
+                self.back_to_back = 0;
+            }
         }
 
-        // based on nintendo gameboy scoring system
-        //TODO: soft drop scoring
-        match cleared_lines.len() {
-            1 => self.score += 40 * (self.level + 1),
-            2 => self.score += 100 * (self.level + 1),
-            3 => self.score += 300 * (self.level + 1),
-            4 => self.score += 1200 * (self.level + 1),
-            _ => {}
+        //every placement in a row that clears lines counts towards a combo
+        if cleared_lines.is_empty() {
+            self.combo = 0;
+        } else {
+            self.combo += 1;
         }
 
-        if cleared_lines.len() as u32 > self.lines_to_clear_before_next_level {
-            self.lines_to_clear_before_next_level = 10;
-            if self.level < MAX_LEVEL as u32 {
-                self.level += 1;
+        let mut rows: Vec<usize> = cleared_lines.into_iter().collect();
+        rows.sort();
+
+        let kind = LineClear {
+            lines: rows.len(),
+            t_spin,
+            combo: self.combo,
+            back_to_back,
+        };
+        self.add_score(self.mode.line_clear_score(&kind));
+
+        if !rows.is_empty() || t_spin != TSpin::None {
+            self.output_events.push(OutputEvent::LinesCleared { kind });
+
+            let level_before = self.get_level();
+            self.mode.lines_cleared(&kind);
+            if self.get_level() > level_before {
+                self.output_events
+                    .push(OutputEvent::LevelUp(self.get_level()));
             }
-            println!("Level: {}", self.level);
-            println!("Score: {}", self.score);
-        } else {
-            self.lines_to_clear_before_next_level -= cleared_lines.len() as u32;
         }
+        self.lines_cleared += rows.len() as u32;
+
+        rows
+    }
 
+    // removes the given rows and drops everything above them down
+    fn collapse_rows(&mut self, rows: &[usize]) {
         let new_field = self.play_field.select(
             Axis(1),
-            &(0..PLAY_FIELD_HEIGHT)
-                .filter(|x| !cleared_lines.contains(x))
+            &(0..self.total_height())
+                .filter(|y| !rows.contains(y))
                 .collect::<Vec<_>>(),
         );
-        let empty_rows = Array2::from_elem((PLAY_FIELD_WIDTH, cleared_lines.len()), Cell::Empty);
+        let empty_rows = Array2::from_elem((self.play_field_width, rows.len()), Cell::Empty);
 
         self.play_field = ndarray::concatenate![Axis(1), new_field, empty_rows];
     }
 
-    pub fn game_tick(&mut self, _: Duration) -> OutputEvent {
+    // runs as many frames as fit in the time given, carrying any remainder over to the next
+    // tick, and returns everything that happened in them. Ticking by FRAME_DURATION steps
+    // exactly one frame
+    pub fn game_tick(&mut self, delta_time: Duration) -> Vec<OutputEvent> {
+        //time spent paused is thrown away so every timer picks up where it left off
+        if !self.paused {
+            self.unsimulated_time += delta_time;
+        }
+        while self.unsimulated_time >= FRAME_DURATION && !self.should_exit {
+            self.unsimulated_time -= FRAME_DURATION;
+            self.step_frame();
+        }
+
         if self.should_exit {
-            return OutputEvent::Exit;
+            self.output_events.push(OutputEvent::Exit);
+        }
+        self.output_events.drain(..).collect()
+    }
+
+    // advances the game by exactly one frame
+    fn step_frame(&mut self) {
+        //nothing moves once the game is over
+        if self.state != GameState::Playing {
+            return;
         }
 
+        if self.mode.is_complete() {
+            self.complete();
+            return;
+        }
+
+        self.mode.tick();
+        self.frame_count += 1;
+
         let actions: Vec<GameAction> = self.action_queue.drain(..).collect();
+
+        //nothing can be done between pieces apart from charging up auto shift
+        if !self.is_falling() {
+            self.input.auto_shift(self.play_field_width);
+            self.step_delay();
+            return;
+        }
+
         for action in actions {
-            let output_event = self.take_action(action);
+            self.take_action(action);
 
-            if let OutputEvent::Exit = output_event {
-                return output_event;
+            if !self.is_falling() {
+                return;
             }
         }
 
-        if self.time_of_last_move.elapsed() > LEVEL_DROP_DURATIONS[self.level as usize] {
+        if let Some((action, repeats)) = self.input.auto_shift(self.play_field_width) {
+            for _ in 0..repeats {
+                self.take_action(action);
+            }
+        }
+
+        let soft_dropping = self.input.is_held(GameAction::MoveDown);
+        //gravity stops getting faster after the last level in the table
+        let drop_level = (self.get_level() as usize).min(MAX_LEVEL - 1);
+        let mut drop_frames = LEVEL_DROP_FRAMES[drop_level];
+        if soft_dropping {
+            drop_frames = (drop_frames / self.soft_drop_factor as u64).max(1);
+        }
+
+        self.frames_since_last_drop += 1;
+        if self.frames_since_last_drop >= drop_frames {
             //auto-tick down
-            let output_event = self.take_action(GameAction::MoveDown);
+            self.move_current_tetromino_down(soft_dropping);
+
+            if !self.is_falling() {
+                return;
+            }
+
+            self.frames_since_last_drop = 0;
+        }
 
-            if let OutputEvent::Exit = output_event {
-                return output_event;
+        //a piece resting on the stack locks once its lock delay runs out
+        if self.lock_delay > 0 {
+            if self.drop_distance() == 0 {
+                self.lock_delay_frames += 1;
+                if self.lock_delay_frames >= self.lock_delay {
+                    self.commit_current_tetromino();
+                }
+            } else {
+                self.lock_delay_frames = 0;
             }
+        }
+    }
 
-            self.time_of_last_move = Instant::now();
+    // counts down the line clear or entry delay between pieces
+    fn step_delay(&mut self) {
+        match &mut self.phase {
+            Phase::Falling => {}
+            Phase::LineClear {
+                rows,
+                frames_left,
+                entry_delay,
+            } => {
+                *frames_left -= 1;
+                if *frames_left == 0 {
+                    let rows = std::mem::take(rows);
+                    let entry_delay = *entry_delay;
+                    self.collapse_rows(&rows);
+                    self.start_entry_delay(entry_delay);
+                }
+            }
+            Phase::Entry { frames_left } => {
+                *frames_left -= 1;
+                if *frames_left == 0 {
+                    self.spawn_next_tetromino();
+                }
+            }
         }
+    }
+}
 
-        OutputEvent::NoOp
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // plays the same inputs on a fixed schedule, a frame at a time
+    fn play_script(tetris: &mut Tetris) {
+        let script = [
+            GameAction::MoveLeft,
+            GameAction::Rotate,
+            GameAction::HardDrop,
+            GameAction::MoveRight,
+            GameAction::RotateCcw,
+            GameAction::MoveDown,
+            GameAction::Hold,
+            GameAction::Rotate180,
+            GameAction::HardDrop,
+        ];
+
+        for (frame, action) in script.iter().cycle().take(300).enumerate() {
+            tetris.recieve_event(InputEvent::Press(*action));
+            //holding some of them for a few frames so auto shift and soft drop kick in
+            for _ in 0..frame % 15 {
+                tetris.game_tick(FRAME_DURATION);
+            }
+            tetris.recieve_event(InputEvent::Release(*action));
+            tetris.game_tick(FRAME_DURATION);
+        }
+    }
+
+    #[test]
+    fn same_seed_and_inputs_play_the_same_game() {
+        let mut first = Tetris::new(Config::guideline(), 1234);
+        let mut second = Tetris::new(Config::guideline(), 1234);
+
+        play_script(&mut first);
+        play_script(&mut second);
+
+        assert!(first.get_statistics().pieces_placed > 0);
+        assert_eq!(first.play_field, second.play_field);
+        assert_eq!(first.score, second.score);
+        assert_eq!(first.get_state(), second.get_state());
+    }
+
+    // if this changes after upgrading rand or rand_chacha then old seeds no longer replay
+    // the same games
+    #[test]
+    fn seed_gives_a_fixed_piece_sequence() {
+        use TetrominoType::*;
+
+        let config = Config {
+            preview_length: 13,
+            ..Config::guideline()
+        };
+        let tetris = Tetris::new(config, 1234);
+
+        let mut pieces = vec![tetris.current_tetromino.tetromino_type];
+        pieces.extend(tetris.next_tetrominoes.iter().copied());
+
+        assert_eq!(
+            pieces,
+            vec![T, S, Square, Z, L, Line, J, L, Z, Line, S, J, T, Square]
+        );
     }
 }
@@ -0,0 +1,112 @@
+use crate::tetris::game_mode::GameModeKind;
+use crate::tetris::level_progression::LevelProgressionKind;
+use crate::tetris::randomizer::RandomizerKind;
+
+// how long the gap between one piece locking and the next appearing is
+#[derive(Debug, Clone, Copy)]
+pub enum EntryDelay {
+    Fixed(u64),
+    //the NES waits 10 frames after a piece locks in the bottom two rows and 2 more for
+    //every 4 rows higher up, to at most 18
+    LockHeight,
+}
+
+impl EntryDelay {
+    // the delay after a piece whose lowest cell is on the given row locks
+    pub fn frames(&self, lock_row: i32) -> u64 {
+        match self {
+            EntryDelay::Fixed(frames) => *frames,
+            EntryDelay::LockHeight => (10 + 2 * ((lock_row.max(0) as u64 + 2) / 4)).min(18),
+        }
+    }
+}
+
+pub struct Config {
+    pub mode: GameModeKind,
+    // the visible play field, 10x20 in almost every version
+    pub play_field_width: usize,
+    pub play_field_height: usize,
+    pub start_level: u32,
+    pub level_progression: LevelProgressionKind,
+    // how many times faster than gravity the piece falls while soft drop is held
+    pub soft_drop_factor: u32,
+    // how many upcoming pieces are shown, 1 on the Game Boy, 5 or 6 in modern guideline games
+    pub preview_length: usize,
+    pub randomizer: RandomizerKind,
+    // frames a piece can sit on the stack before it locks, 0 locks it as soon as it lands
+    pub lock_delay: u64,
+    // how many times moving or rotating a grounded piece can restart its lock delay
+    pub max_lock_resets: u32,
+    // frames left or right has to be held before the piece starts sliding on its own
+    pub das: u64,
+    // frames between each step once it's sliding, 0 slides straight to the wall
+    pub arr: u64,
+    // whether a piece that locks with only some of its cells above the play field ends the
+    // game, a piece locked entirely above it always does
+    pub partial_lock_out: bool,
+    // hidden rows above the visible play field that pieces spawn into and can lock in
+    pub vanish_zone_height: usize,
+    // frames between a piece locking and the next one spawning, after any line clear delay
+    pub entry_delay: EntryDelay,
+    // frames cleared lines stay on the board before the rows above them fall
+    pub line_clear_delay: u64,
+}
+
+impl Config {
+    // modern guideline rules with a 7-bag, long preview and move reset lock delay
+    pub fn guideline() -> Self {
+        Config {
+            preview_length: 5,
+            randomizer: RandomizerKind::SevenBag,
+            level_progression: LevelProgressionKind::FixedGoal,
+            lock_delay: 30,
+            max_lock_resets: 15,
+            das: 10,
+            arr: 2,
+            partial_lock_out: false,
+            entry_delay: EntryDelay::Fixed(0),
+            line_clear_delay: 0,
+            ..Config::default()
+        }
+    }
+
+    // the NES version, which pauses between pieces for longer the higher up the last one
+    // locked
+    pub fn nes() -> Self {
+        Config {
+            level_progression: LevelProgressionKind::Nes,
+            randomizer: RandomizerKind::Nes,
+            das: 16,
+            arr: 6,
+            entry_delay: EntryDelay::LockHeight,
+            //really 17 to 20 depending on where the frame counter is when the lines clear
+            line_clear_delay: 17,
+            ..Config::default()
+        }
+    }
+}
+
+// plays like the Game Boy original
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            mode: GameModeKind::Marathon,
+            play_field_width: 10,
+            play_field_height: 20,
+            start_level: 0,
+            level_progression: LevelProgressionKind::GameBoy,
+            soft_drop_factor: 20,
+            preview_length: 1,
+            randomizer: RandomizerKind::Uniform,
+            lock_delay: 0,
+            max_lock_resets: 0,
+            das: 24,
+            arr: 9,
+            partial_lock_out: true,
+            vanish_zone_height: 20,
+            entry_delay: EntryDelay::Fixed(2),
+            //the Game Boy's clear animation holds the board for about a second and a half
+            line_clear_delay: 91,
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use ndarray::ArrayViewMut2;
+use rand::{Rng, RngCore};
+
+use crate::tetris::level_progression::{LevelProgression, LevelProgressionKind};
+use crate::tetris::tetromino::{Tetromino, TetrominoType};
+use crate::tetris::{Cell, LineClear, TSpin, from_frames};
+
+// rows of garbage a B-Type game starts with for each "high" setting
+const B_TYPE_GARBAGE_ROWS: [usize; 6] = [0, 3, 5, 8, 10, 12];
+const B_TYPE_LINE_GOAL: u32 = 25;
+
+// what the player is trying to do, how it's scored and when the game is won. Topping out
+// isn't up to the mode, it always ends the game
+pub trait GameMode {
+    fn level(&self) -> u32;
+
+    // called once before the first piece spawns with the visible play field
+    fn start(&mut self, _play_field: ArrayViewMut2<Cell>, _rng: &mut dyn RngCore) {}
+
+    // points for a piece that just locked, given what it cleared. This is asked for every
+    // piece, including ones that cleared nothing
+    fn line_clear_score(&self, line_clear: &LineClear) -> u32 {
+        //based on nintendo gameboy scoring system, T-spins aren't in the original so they
+        //score as in the modern guideline
+        let mut score = match (line_clear.t_spin, line_clear.lines) {
+            (TSpin::None, 1) => 40,
+            (TSpin::None, 2) => 100,
+            (TSpin::None, 3) => 300,
+            (TSpin::None, 4) => 1200,
+            (TSpin::Mini, 0) => 100,
+            (TSpin::Mini, 1) => 200,
+            (TSpin::Mini, 2) => 400,
+            (TSpin::Full, 0) => 400,
+            (TSpin::Full, 1) => 800,
+            (TSpin::Full, 2) => 1200,
+            (TSpin::Full, 3) => 1600,
+            _ => 0,
+        };
+
+        //back to back clears score half as much again, and every clear in a combo after the
+        //first earns a bonus
+        if line_clear.back_to_back {
+            score = score * 3 / 2;
+        }
+        if line_clear.lines > 0 {
+            score += 50 * (line_clear.combo - 1);
+        }
+
+        score * (self.level() + 1)
+    }
+
+    // lets the mode follow along with clears, e.g. to level up or count towards a goal. Only
+    // called for pieces that cleared lines or were a T-spin
+    fn lines_cleared(&mut self, _line_clear: &LineClear) {}
+
+    fn piece_locked(&mut self, _tetromino: &Tetromino) {}
+
+    // called once for every frame of play
+    fn tick(&mut self) {}
+
+    // once this is true the game ends as completed
+    fn is_complete(&self) -> bool {
+        false
+    }
+
+    // points added to the score when the game is completed
+    fn completion_bonus(&self) -> u32 {
+        0
+    }
+
+    // how many more lines have to be cleared, in modes with a line goal
+    fn lines_remaining(&self) -> Option<u32> {
+        None
+    }
+
+    // the time left and what fraction of the time limit that is, in modes that have one
+    fn countdown(&self) -> Option<(Duration, f32)> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameModeKind {
+    //play for as long as possible as the game speeds up
+    Marathon,
+    //clear the given number of lines as quickly as possible at a fixed speed
+    Sprint { line_goal: u32 },
+    //score as much as possible before the time runs out
+    Ultra { frame_limit: u64 },
+    //the Game Boy's B-Type, clear 25 lines starting from a stack of garbage as high as the
+    //"high" setting, 0 to 5, asks for
+    BType { high: usize },
+}
+
+impl GameModeKind {
+    // only marathons use the level progression, the other modes stay on the start level
+    pub fn create(
+        &self,
+        start_level: u32,
+        level_progression: LevelProgressionKind,
+    ) -> Box<dyn GameMode> {
+        match *self {
+            GameModeKind::Marathon => {
+                Box::new(MarathonMode::new(level_progression.create(start_level)))
+            }
+            GameModeKind::Sprint { line_goal } => Box::new(SprintMode::new(start_level, line_goal)),
+            GameModeKind::Ultra { frame_limit } => {
+                Box::new(UltraMode::new(start_level, frame_limit))
+            }
+            GameModeKind::BType { high } => Box::new(BTypeMode::new(start_level, high)),
+        }
+    }
+}
+
+pub struct MarathonMode {
+    level_progression: Box<dyn LevelProgression>,
+}
+
+impl MarathonMode {
+    pub fn new(level_progression: Box<dyn LevelProgression>) -> Self {
+        Self { level_progression }
+    }
+}
+
+impl GameMode for MarathonMode {
+    fn level(&self) -> u32 {
+        self.level_progression.level()
+    }
+
+    fn lines_cleared(&mut self, line_clear: &LineClear) {
+        self.level_progression.lines_cleared(line_clear);
+    }
+}
+
+pub struct SprintMode {
+    level: u32,
+    line_goal: u32,
+    lines: u32,
+}
+
+impl SprintMode {
+    pub fn new(level: u32, line_goal: u32) -> Self {
+        Self {
+            level,
+            line_goal,
+            lines: 0,
+        }
+    }
+}
+
+impl GameMode for SprintMode {
+    fn level(&self) -> u32 {
+        self.level
+    }
+
+    fn lines_cleared(&mut self, line_clear: &LineClear) {
+        self.lines += line_clear.lines as u32;
+    }
+
+    fn is_complete(&self) -> bool {
+        self.lines >= self.line_goal
+    }
+
+    fn lines_remaining(&self) -> Option<u32> {
+        Some(self.line_goal.saturating_sub(self.lines))
+    }
+}
+
+pub struct UltraMode {
+    level: u32,
+    frame_limit: u64,
+    frames: u64,
+}
+
+impl UltraMode {
+    pub fn new(level: u32, frame_limit: u64) -> Self {
+        Self {
+            level,
+            frame_limit,
+            frames: 0,
+        }
+    }
+}
+
+impl GameMode for UltraMode {
+    fn level(&self) -> u32 {
+        self.level
+    }
+
+    fn tick(&mut self) {
+        self.frames += 1;
+    }
+
+    fn is_complete(&self) -> bool {
+        self.frames >= self.frame_limit
+    }
+
+    fn countdown(&self) -> Option<(Duration, f32)> {
+        let frames_left = self.frame_limit.saturating_sub(self.frames);
+        Some((
+            from_frames(frames_left),
+            frames_left as f32 / self.frame_limit as f32,
+        ))
+    }
+}
+
+pub struct BTypeMode {
+    level: u32,
+    high: usize,
+    lines: u32,
+}
+
+impl BTypeMode {
+    // the high setting only goes up to 5, anything above that is treated as 5
+    pub fn new(level: u32, high: usize) -> Self {
+        Self {
+            level,
+            high: high.min(B_TYPE_GARBAGE_ROWS.len() - 1),
+            lines: 0,
+        }
+    }
+}
+
+impl GameMode for BTypeMode {
+    fn level(&self) -> u32 {
+        self.level
+    }
+
+    // fills the bottom rows with random blocks, always leaving at least one gap in each row
+    fn start(&mut self, mut play_field: ArrayViewMut2<Cell>, rng: &mut dyn RngCore) {
+        let (width, height) = play_field.dim();
+        let rows = B_TYPE_GARBAGE_ROWS[self.high].min(height - 1);
+
+        for y in 0..rows {
+            for x in 0..width {
+                if rng.random_bool(0.5) {
+                    play_field[[x, y]] = Cell::Block(TetrominoType::random(rng));
+                }
+            }
+
+            let gap = rng.random_range(0..width);
+            play_field[[gap, y]] = Cell::Empty;
+        }
+    }
+
+    fn lines_cleared(&mut self, line_clear: &LineClear) {
+        self.lines += line_clear.lines as u32;
+    }
+
+    fn is_complete(&self) -> bool {
+        self.lines >= B_TYPE_LINE_GOAL
+    }
+
+    fn lines_remaining(&self) -> Option<u32> {
+        Some(B_TYPE_LINE_GOAL.saturating_sub(self.lines))
+    }
+}
@@ -0,0 +1,116 @@
+use crate::tetris::GameAction;
+
+// tracks which buttons are held so that shifting auto repeats on the game's own clock
+// rather than relying on the OS key repeat
+pub struct InputState {
+    // frames a shift has to be held before it starts repeating (delayed auto shift)
+    das: u64,
+    // frames between each repeat once it has started (auto repeat rate), 0 shifts straight
+    // to the wall
+    arr: u64,
+    held: Vec<GameAction>,
+    // frames the current shift direction has been held for before this one, the frame it was
+    // pressed on is frame 0
+    shift_frames: u64,
+}
+
+impl InputState {
+    pub fn new(das: u64, arr: u64) -> Self {
+        InputState {
+            das,
+            arr,
+            held: Vec::new(),
+            shift_frames: 0,
+        }
+    }
+
+    pub fn press(&mut self, action: GameAction) {
+        if !self.is_held(action) {
+            self.held.push(action);
+        }
+
+        if let GameAction::MoveLeft | GameAction::MoveRight = action {
+            self.shift_frames = 0;
+        }
+    }
+
+    pub fn release(&mut self, action: GameAction) {
+        let was_shifting = self.shift_direction() == Some(action);
+        self.held.retain(|held| *held != action);
+
+        //falling back to the other direction has to wait out the delay again
+        if was_shifting {
+            self.shift_frames = 0;
+        }
+    }
+
+    pub fn is_held(&self, action: GameAction) -> bool {
+        self.held.contains(&action)
+    }
+
+    // the most recently pressed of left and right wins when both are held
+    fn shift_direction(&self) -> Option<GameAction> {
+        self.held
+            .iter()
+            .rev()
+            .find(|action| matches!(action, GameAction::MoveLeft | GameAction::MoveRight))
+            .copied()
+    }
+
+    // advances by a frame and returns the shift to repeat, if any, and how many times
+    pub fn auto_shift(&mut self, max_repeats: usize) -> Option<(GameAction, usize)> {
+        let direction = self.shift_direction()?;
+        let held_frames = self.shift_frames;
+        self.shift_frames += 1;
+
+        //so the first repeat comes exactly das frames after the press
+        if held_frames < self.das {
+            return None;
+        }
+
+        if self.arr == 0 {
+            return Some((direction, max_repeats));
+        }
+
+        let frames_repeating = held_frames - self.das;
+        if frames_repeating.is_multiple_of(self.arr) {
+            Some((direction, 1))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // presses right on frame 0 and returns the frames it repeated on
+    fn repeat_frames(das: u64, arr: u64, frames: u64) -> Vec<u64> {
+        let mut input = InputState::new(das, arr);
+        input.press(GameAction::MoveRight);
+
+        (0..frames)
+            .filter(|_| input.auto_shift(10).is_some())
+            .collect()
+    }
+
+    #[test]
+    fn first_repeats_after_das() {
+        //guideline
+        assert_eq!(repeat_frames(10, 2, 15), vec![10, 12, 14]);
+        //NES
+        assert_eq!(repeat_frames(16, 6, 23), vec![16, 22]);
+    }
+
+    #[test]
+    fn zero_arr_shifts_to_the_wall() {
+        let mut input = InputState::new(10, 0);
+        input.press(GameAction::MoveLeft);
+
+        for _ in 0..10 {
+            assert_eq!(input.auto_shift(10), None);
+        }
+        assert_eq!(input.auto_shift(10), Some((GameAction::MoveLeft, 10)));
+    }
+}
@@ -0,0 +1,295 @@
+use std::str::FromStr;
+
+use crate::tetris::{LineClear, TSpin};
+
+// decides when the game moves up a level
+pub trait LevelProgression {
+    fn level(&self) -> u32;
+
+    // called every time a piece locks that cleared lines or was a T-spin
+    fn lines_cleared(&mut self, line_clear: &LineClear);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LevelProgressionKind {
+    GameBoy,
+    Nes,
+    FixedGoal,
+    VariableGoal,
+}
+
+impl LevelProgressionKind {
+    pub fn create(&self, start_level: u32) -> Box<dyn LevelProgression> {
+        match self {
+            LevelProgressionKind::GameBoy => Box::new(GameBoyProgression::new(start_level)),
+            LevelProgressionKind::Nes => Box::new(NesProgression::new(start_level)),
+            LevelProgressionKind::FixedGoal => Box::new(FixedGoalProgression::new(start_level)),
+            LevelProgressionKind::VariableGoal => {
+                Box::new(VariableGoalProgression::new(start_level))
+            }
+        }
+    }
+}
+
+impl FromStr for LevelProgressionKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "gameboy" => Ok(LevelProgressionKind::GameBoy),
+            "nes" => Ok(LevelProgressionKind::Nes),
+            "fixed" => Ok(LevelProgressionKind::FixedGoal),
+            "variable" => Ok(LevelProgressionKind::VariableGoal),
+            _ => Err(format!("unknown level progression: {name}")),
+        }
+    }
+}
+
+// Game Boy marathon: the first level up comes after start level x 10 + 10 lines, then every
+// 10 lines after that, which works out as a level for every 10 lines once past the start level
+pub struct GameBoyProgression {
+    start_level: u32,
+    lines: u32,
+}
+
+impl GameBoyProgression {
+    pub fn new(start_level: u32) -> Self {
+        Self {
+            start_level,
+            lines: 0,
+        }
+    }
+}
+
+impl LevelProgression for GameBoyProgression {
+    fn level(&self) -> u32 {
+        self.start_level.max(self.lines / 10)
+    }
+
+    fn lines_cleared(&mut self, line_clear: &LineClear) {
+        self.lines += line_clear.lines as u32;
+    }
+}
+
+// NES: the first level up comes after min(start x 10 + 10, max(100, start x 10 - 50)) lines,
+// then every 10 lines after that
+pub struct NesProgression {
+    start_level: u32,
+    lines: u32,
+}
+
+impl NesProgression {
+    pub fn new(start_level: u32) -> Self {
+        Self {
+            start_level,
+            lines: 0,
+        }
+    }
+
+    fn first_transition(&self) -> u32 {
+        let start_lines = self.start_level * 10;
+        (start_lines + 10).min(100.max(start_lines.saturating_sub(50)))
+    }
+}
+
+impl LevelProgression for NesProgression {
+    fn level(&self) -> u32 {
+        let first_transition = self.first_transition();
+        if self.lines < first_transition {
+            self.start_level
+        } else {
+            self.start_level + 1 + (self.lines - first_transition) / 10
+        }
+    }
+
+    fn lines_cleared(&mut self, line_clear: &LineClear) {
+        self.lines += line_clear.lines as u32;
+    }
+}
+
+const FIXED_GOAL_LINES: u32 = 10;
+
+// guideline fixed goal: every 10 lines is a level up, whatever level the game started on
+pub struct FixedGoalProgression {
+    start_level: u32,
+    lines: u32,
+}
+
+impl FixedGoalProgression {
+    pub fn new(start_level: u32) -> Self {
+        Self {
+            start_level,
+            lines: 0,
+        }
+    }
+}
+
+impl LevelProgression for FixedGoalProgression {
+    fn level(&self) -> u32 {
+        self.start_level + self.lines / FIXED_GOAL_LINES
+    }
+
+    fn lines_cleared(&mut self, line_clear: &LineClear) {
+        self.lines += line_clear.lines as u32;
+    }
+}
+
+// guideline variable goal: each level needs 5 x its (1-based) number in goal points, harder
+// clears are worth more points and back to back clears half as much again. Points left over
+// from one level carry into the next
+pub struct VariableGoalProgression {
+    level: u32,
+    goal_points: u32,
+}
+
+impl VariableGoalProgression {
+    pub fn new(start_level: u32) -> Self {
+        Self {
+            level: start_level,
+            goal_points: 0,
+        }
+    }
+
+    fn goal(&self) -> u32 {
+        5 * (self.level + 1)
+    }
+}
+
+impl LevelProgression for VariableGoalProgression {
+    fn level(&self) -> u32 {
+        self.level
+    }
+
+    fn lines_cleared(&mut self, line_clear: &LineClear) {
+        let mut points = match (line_clear.t_spin, line_clear.lines) {
+            (TSpin::None, 1) => 1,
+            (TSpin::None, 2) => 3,
+            (TSpin::None, 3) => 5,
+            (TSpin::None, 4) => 8,
+            (TSpin::Mini, 0) => 1,
+            (TSpin::Mini, 1) => 2,
+            (TSpin::Mini, 2) => 4,
+            (TSpin::Full, 0) => 4,
+            (TSpin::Full, 1) => 8,
+            (TSpin::Full, 2) => 12,
+            (TSpin::Full, 3) => 16,
+            _ => 0,
+        };
+        if line_clear.back_to_back {
+            points = points * 3 / 2;
+        }
+
+        self.goal_points += points;
+        while self.goal_points >= self.goal() {
+            self.goal_points -= self.goal();
+            self.level += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear(lines: usize) -> LineClear {
+        LineClear {
+            lines,
+            t_spin: TSpin::None,
+            combo: 1,
+            back_to_back: false,
+        }
+    }
+
+    // clears single lines until the level changes and returns how many it took
+    fn lines_until_level_up(progression: &mut dyn LevelProgression) -> u32 {
+        let start_level = progression.level();
+        let mut lines = 0;
+        while progression.level() == start_level {
+            progression.lines_cleared(&clear(1));
+            lines += 1;
+        }
+        lines
+    }
+
+    #[test]
+    fn game_boy_first_level_up() {
+        let mut progression = GameBoyProgression::new(5);
+        assert_eq!(lines_until_level_up(&mut progression), 60);
+        assert_eq!(progression.level(), 6);
+        assert_eq!(lines_until_level_up(&mut progression), 10);
+
+        let mut progression = GameBoyProgression::new(0);
+        assert_eq!(lines_until_level_up(&mut progression), 10);
+    }
+
+    #[test]
+    fn nes_first_transition() {
+        for (start_level, lines) in [
+            (0, 10),
+            (9, 100),
+            (10, 100),
+            (15, 100),
+            (16, 110),
+            (19, 140),
+        ] {
+            assert_eq!(
+                NesProgression::new(start_level).first_transition(),
+                lines,
+                "start level {start_level}"
+            );
+        }
+
+        let mut progression = NesProgression::new(16);
+        assert_eq!(lines_until_level_up(&mut progression), 110);
+        assert_eq!(progression.level(), 17);
+        assert_eq!(lines_until_level_up(&mut progression), 10);
+    }
+
+    #[test]
+    fn fixed_goal_every_ten_lines() {
+        for start_level in [0, 5, 15] {
+            let mut progression = FixedGoalProgression::new(start_level);
+            for level in start_level + 1..start_level + 4 {
+                assert_eq!(lines_until_level_up(&mut progression), 10);
+                assert_eq!(progression.level(), level);
+            }
+        }
+    }
+
+    #[test]
+    fn variable_goal_carries_points_over() {
+        let mut progression = VariableGoalProgression::new(0);
+
+        //a Tetris is 8 points against a goal of 5, leaving 3 towards the next goal of 10
+        progression.lines_cleared(&clear(4));
+        assert_eq!(progression.level(), 1);
+        assert_eq!(progression.goal_points, 3);
+
+        //3 + 8 clears the goal of 10 with 1 left over
+        progression.lines_cleared(&clear(4));
+        assert_eq!(progression.level(), 2);
+        assert_eq!(progression.goal_points, 1);
+    }
+
+    #[test]
+    fn variable_goal_back_to_back_bonus() {
+        let mut progression = VariableGoalProgression::new(1);
+
+        //a back to back Tetris is 8 x 1.5 = 12 points against a goal of 10
+        progression.lines_cleared(&LineClear {
+            back_to_back: true,
+            ..clear(4)
+        });
+        assert_eq!(progression.level(), 2);
+        assert_eq!(progression.goal_points, 2);
+
+        //a back to back T-spin single is 8 x 1.5 = 12 points
+        progression.lines_cleared(&LineClear {
+            t_spin: TSpin::Full,
+            back_to_back: true,
+            ..clear(1)
+        });
+        assert_eq!(progression.level(), 2);
+        assert_eq!(progression.goal_points, 14);
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+use crate::tetris::tetromino::TetrominoType;
+
+// decides which piece comes next, all randomness comes from the game's rng so that
+// seeded games are reproducible
+pub trait Randomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrominoType;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum RandomizerKind {
+    Uniform,
+    SevenBag,
+    Nes,
+    GameBoy,
+    Tgm,
+}
+
+impl RandomizerKind {
+    pub fn create(&self) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerKind::Uniform => Box::new(UniformRandomizer),
+            RandomizerKind::SevenBag => Box::new(SevenBagRandomizer::default()),
+            RandomizerKind::Nes => Box::new(NesRandomizer::default()),
+            RandomizerKind::GameBoy => Box::new(GameBoyRandomizer::default()),
+            RandomizerKind::Tgm => Box::new(TgmRandomizer::default()),
+        }
+    }
+}
+
+impl FromStr for RandomizerKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "uniform" => Ok(RandomizerKind::Uniform),
+            "bag" => Ok(RandomizerKind::SevenBag),
+            "nes" => Ok(RandomizerKind::Nes),
+            "gameboy" => Ok(RandomizerKind::GameBoy),
+            "tgm" => Ok(RandomizerKind::Tgm),
+            _ => Err(format!("unknown randomizer: {name}")),
+        }
+    }
+}
+
+// every piece is equally likely every time
+pub struct UniformRandomizer;
+
+impl Randomizer for UniformRandomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrominoType {
+        TetrominoType::random(rng)
+    }
+}
+
+// deals out a shuffled bag of all seven pieces before refilling it, as in modern guideline games
+#[derive(Default)]
+pub struct SevenBagRandomizer {
+    bag: Vec<TetrominoType>,
+}
+
+impl Randomizer for SevenBagRandomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrominoType {
+        if self.bag.is_empty() {
+            self.bag = TetrominoType::ALL_TYPES.to_vec();
+            self.bag.shuffle(rng);
+        }
+        self.bag.pop().unwrap()
+    }
+}
+
+// the NES rolls an eighth "reroll" option alongside the seven pieces, if it gets that or a
+// repeat of the last piece it rolls once more and takes whatever comes up
+#[derive(Default)]
+pub struct NesRandomizer {
+    last: Option<TetrominoType>,
+}
+
+impl Randomizer for NesRandomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrominoType {
+        let roll = rng.random_range(0..=TetrominoType::ALL_TYPES.len());
+
+        let tetromino_type = match TetrominoType::ALL_TYPES.get(roll) {
+            Some(&tetromino_type) if Some(tetromino_type) != self.last => tetromino_type,
+            _ => TetrominoType::random(rng),
+        };
+
+        self.last = Some(tetromino_type);
+        tetromino_type
+    }
+}
+
+const GAME_BOY_ROLLS: usize = 3;
+
+// the Game Boy's own piece numbering, each piece's ID is 4 times its index here. The reroll
+// check works on these IDs so the order matters for how often each piece comes up
+const GAME_BOY_PIECE_ORDER: [TetrominoType; 7] = [
+    TetrominoType::L,
+    TetrominoType::J,
+    TetrominoType::Line,
+    TetrominoType::Square,
+    TetrominoType::Z,
+    TetrominoType::S,
+    TetrominoType::T,
+];
+
+// the Game Boy makes up to three rolls, rerolling whenever the candidate's ID ORed together
+// with the previous two pieces' comes out the same as the previous piece's
+#[derive(Default)]
+pub struct GameBoyRandomizer {
+    previous: usize,
+    before_previous: usize,
+}
+
+impl Randomizer for GameBoyRandomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrominoType {
+        let mut candidate = 0;
+        for _ in 0..GAME_BOY_ROLLS {
+            candidate = rng.random_range(0..GAME_BOY_PIECE_ORDER.len()) * 4;
+            if candidate | self.previous | self.before_previous != self.previous {
+                break;
+            }
+        }
+
+        self.before_previous = self.previous;
+        self.previous = candidate;
+        GAME_BOY_PIECE_ORDER[candidate / 4]
+    }
+}
+
+const TGM_HISTORY_LENGTH: usize = 4;
+const TGM_ROLLS: usize = 4;
+
+// the original Tetris The Grand Master keeps a history of the last four pieces and makes up
+// to four rolls to find a piece that isn't in it, the first piece is never an S, Z or O
+pub struct TgmRandomizer {
+    history: VecDeque<TetrominoType>,
+    first: bool,
+}
+
+impl Default for TgmRandomizer {
+    fn default() -> Self {
+        Self {
+            history: VecDeque::from([TetrominoType::Z; TGM_HISTORY_LENGTH]),
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for TgmRandomizer {
+    fn next(&mut self, rng: &mut dyn RngCore) -> TetrominoType {
+        let tetromino_type = if self.first {
+            self.first = false;
+            const FIRST_TYPES: [TetrominoType; 4] = [
+                TetrominoType::Line,
+                TetrominoType::T,
+                TetrominoType::L,
+                TetrominoType::J,
+            ];
+            FIRST_TYPES[rng.random_range(0..FIRST_TYPES.len())]
+        } else {
+            let mut candidate = TetrominoType::random(rng);
+            for _ in 1..TGM_ROLLS {
+                if !self.history.contains(&candidate) {
+                    break;
+                }
+                candidate = TetrominoType::random(rng);
+            }
+            candidate
+        };
+
+        self.history.pop_front();
+        self.history.push_back(tetromino_type);
+        tetromino_type
+    }
+}
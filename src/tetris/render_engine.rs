@@ -1,7 +1,10 @@
-use crate::tetris::Tetris;
+use crate::tetris::{OutputEvent, Tetris};
 
 pub trait RenderEngine {
     fn render(&mut self, tetris: &Tetris);
+
+    // lets the renderer react to things happening in the game, e.g. to show a T-spin
+    fn handle_event(&mut self, _output_event: &OutputEvent) {}
 }
 
 pub mod sdl;
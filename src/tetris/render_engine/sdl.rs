@@ -1,6 +1,7 @@
 use crate::tetris::render_engine::RenderEngine;
 use crate::tetris::{
-    Cell, Tetris, tetromino::Position, tetromino::Tetromino, tetromino::TetrominoType,
+    Cell, GameState, OutputEvent, TSpin, Tetris, tetromino::Position, tetromino::Tetromino,
+    tetromino::TetrominoType,
 };
 
 use sdl2::Sdl;
@@ -9,17 +10,55 @@ use sdl2::rect::Rect;
 use sdl2::render::Canvas;
 use sdl2::video::Window;
 
-use crate::tetris;
-
 pub const CELL_SIZE: i32 = 30;
+// width in cells of the panel to the right of the play field
+pub const SIDEBAR_WIDTH: i32 = 6;
+// sidebar rows the held piece and the first upcoming piece are centred on
+const HOLD_ROW: i32 = 2;
+const NEXT_ROW: i32 = 6;
+// rows between each upcoming piece
+const NEXT_SPACING: i32 = 3;
+// sidebar rows showing the combo and back to back counters as a row of pips
+const COMBO_ROW: i32 = 0;
+const BACK_TO_BACK_ROW: i32 = 4;
+const PIP_SIZE: i32 = CELL_SIZE / 2;
+const COUNTDOWN_COLOR: SDL2Color = SDL2Color::RGB(49, 199, 239);
 
 const GRID_LINE_COLOR: SDL2Color = SDL2Color::RGB(128, 128, 128);
+const COMBO_COLOR: SDL2Color = SDL2Color::RGB(230, 230, 230);
+const BACK_TO_BACK_COLOR: SDL2Color = SDL2Color::RGB(255, 190, 0);
 const BACKGROUND_COLOR: SDL2Color = SDL2Color::RGB(20, 20, 20);
+const GAME_OVER_COLOR: SDL2Color = SDL2Color::RGB(200, 0, 0);
+const COMPLETED_COLOR: SDL2Color = SDL2Color::RGB(0, 200, 0);
+// how many frames the play field border flashes for after a notable line clear
+const FLASH_FRAMES: u32 = 30;
+const FLASH_THICKNESS: i32 = 4;
+// how many frames a piece is highlighted for after it locks
+const LOCK_FLASH_FRAMES: u32 = 4;
+const LOCK_FLASH_COLOR: SDL2Color = SDL2Color::RGB(255, 255, 255);
+// how far inside its cell the ghost piece outline is drawn, so the gridlines don't hide it
+const GHOST_INSET: i32 = 3;
 
 pub struct SDL2RenderEngine {
     canvas: Canvas<Window>,
+    //size of the visible play field in cells
+    columns: i32,
+    rows: i32,
+    //and in pixels
     width: i32,
     height: i32,
+    //colour to flash the border of the play field and how many frames are left
+    flash: Option<(SDL2Color, u32)>,
+    //the most recently locked piece and how many frames it's still highlighted for
+    lock_flash: Option<(Tetromino, u32)>,
+    //there's no text rendering so these are shown in the window title
+    score: u32,
+    level: u32,
+    paused: bool,
+    //whole seconds left in timed modes
+    seconds_left: Option<u64>,
+    //lines left in modes with a line goal
+    lines_left: Option<u32>,
 }
 
 fn get_tetronimo_colour(tetromino_type: TetrominoType) -> SDL2Color {
@@ -35,13 +74,16 @@ fn get_tetronimo_colour(tetromino_type: TetrominoType) -> SDL2Color {
 }
 
 impl SDL2RenderEngine {
-    pub fn new(sdl_context: &Sdl) -> Self {
-        let width = CELL_SIZE * tetris::PLAY_FIELD_WIDTH as i32;
-        let height = CELL_SIZE * tetris::PLAY_FIELD_HEIGHT as i32;
+    // the window is sized to fit a play field of the given width and height
+    pub fn new(sdl_context: &Sdl, (columns, rows): (usize, usize)) -> Self {
+        let (columns, rows) = (columns as i32, rows as i32);
+        let width = CELL_SIZE * columns;
+        let height = CELL_SIZE * rows;
+        let window_width = width + CELL_SIZE * SIDEBAR_WIDTH;
         let video_subsystem = sdl_context.video().unwrap();
 
         let window = video_subsystem
-            .window("Tetris", width as u32, height as u32)
+            .window("Tetris", window_width as u32, height as u32)
             .position_centered()
             .build()
             .unwrap();
@@ -50,16 +92,33 @@ impl SDL2RenderEngine {
 
         Self {
             canvas,
+            columns,
+            rows,
             width,
             height,
+            flash: None,
+            lock_flash: None,
+            score: 0,
+            level: 0,
+            paused: false,
+            seconds_left: None,
+            lines_left: None,
         }
     }
 
+    // cells in the vanish zone above the play field aren't drawn
     fn draw_cell(&mut self, position: Position, color: SDL2Color) {
         let (x_i32, y_i32) = position;
+        if y_i32 >= self.rows {
+            return;
+        }
         let screen_x = x_i32 * CELL_SIZE;
-        let screen_y = (tetris::PLAY_FIELD_HEIGHT as i32 - 1 - y_i32) * CELL_SIZE;
+        let screen_y = (self.rows - 1 - y_i32) * CELL_SIZE;
 
+        self.fill_cell(screen_x, screen_y, color);
+    }
+
+    fn fill_cell(&mut self, screen_x: i32, screen_y: i32, color: SDL2Color) {
         let rect = Rect::new(screen_x, screen_y, CELL_SIZE as u32, CELL_SIZE as u32);
         self.canvas.set_draw_color(color);
         let _ = self.canvas.fill_rect(rect);
@@ -79,11 +138,151 @@ impl SDL2RenderEngine {
         }
     }
 
+    fn draw_ghost_tetromino(&mut self, current_tetromino: &Tetromino, position: Position) {
+        let (x, y) = position;
+        self.canvas
+            .set_draw_color(get_tetronimo_colour(current_tetromino.tetromino_type));
+
+        for (x_offset, y_offset) in current_tetromino.get_positions() {
+            if y + y_offset >= self.rows {
+                continue;
+            }
+            let screen_x = (x + x_offset) * CELL_SIZE;
+            let screen_y = (self.rows - 1 - (y + y_offset)) * CELL_SIZE;
+
+            //two pixels thick so it stands out from the gridlines
+            for inset in [GHOST_INSET, GHOST_INSET + 1] {
+                let size = (CELL_SIZE - 2 * inset) as u32;
+                let _ = self.canvas.draw_rect(Rect::new(
+                    screen_x + inset,
+                    screen_y + inset,
+                    size,
+                    size,
+                ));
+            }
+        }
+    }
+
+    // draws a tetromino in its spawn orientation in the sidebar, centred on the given row
+    fn draw_sidebar_tetromino(&mut self, tetromino_type: TetrominoType, row: i32) {
+        let origin_x = self.width + (SIDEBAR_WIDTH / 2 - 1) * CELL_SIZE;
+        let origin_y = row * CELL_SIZE;
+
+        for (x_offset, y_offset) in Tetromino::new(tetromino_type, (0, 0)).get_positions() {
+            self.fill_cell(
+                origin_x + x_offset * CELL_SIZE,
+                origin_y - y_offset * CELL_SIZE,
+                get_tetronimo_colour(tetromino_type),
+            );
+        }
+    }
+
+    // draws up to a sidebar's width of pips to show a counter
+    fn draw_pips(&mut self, count: u32, row: i32, color: SDL2Color) {
+        let max_pips = (SIDEBAR_WIDTH * CELL_SIZE / PIP_SIZE) / 2;
+        self.canvas.set_draw_color(color);
+
+        for pip in 0..(count as i32).min(max_pips) {
+            let screen_x = self.width + PIP_SIZE / 2 + pip * PIP_SIZE * 2;
+            let screen_y = row * CELL_SIZE + (CELL_SIZE - PIP_SIZE) / 2;
+            let _ = self.canvas.fill_rect(Rect::new(
+                screen_x,
+                screen_y,
+                PIP_SIZE as u32,
+                PIP_SIZE as u32,
+            ));
+        }
+    }
+
+    fn draw_lock_flash(&mut self) {
+        let Some((locked_tetromino, frames_left)) = self.lock_flash.take() else {
+            return;
+        };
+
+        let (x, y) = locked_tetromino.position;
+        for (x_offset, y_offset) in locked_tetromino.get_positions() {
+            self.draw_cell((x + x_offset, y + y_offset), LOCK_FLASH_COLOR);
+        }
+
+        self.lock_flash = (frames_left > 1).then_some((locked_tetromino, frames_left - 1));
+    }
+
+    // a bar along the bottom of the sidebar that shrinks as the time runs out
+    fn draw_countdown(&mut self, fraction_left: f32) {
+        let max_width = SIDEBAR_WIDTH * CELL_SIZE - PIP_SIZE;
+        let bar_width = (max_width as f32 * fraction_left) as u32;
+        if bar_width == 0 {
+            return;
+        }
+
+        self.canvas.set_draw_color(COUNTDOWN_COLOR);
+        let _ = self.canvas.fill_rect(Rect::new(
+            self.width + PIP_SIZE / 2,
+            (self.rows - 1) * CELL_SIZE + (CELL_SIZE - PIP_SIZE) / 2,
+            bar_width,
+            PIP_SIZE as u32,
+        ));
+    }
+
+    // the cleared rows are wiped away from the middle outwards over the line clear delay
+    fn draw_line_clear(&mut self, rows: &[usize], progress: f32) {
+        let centre = self.columns as f32 / 2.0;
+        let wiped = progress * centre;
+
+        for row in rows {
+            for x in 0..self.columns {
+                let distance = (x as f32 + 0.5 - centre).abs();
+                if distance < wiped {
+                    self.draw_cell((x, *row as i32), BACKGROUND_COLOR);
+                }
+            }
+        }
+    }
+
+    fn update_title(&mut self) {
+        let mut title = format!("Tetris - Level {} - Score {}", self.level, self.score);
+        if let Some(lines_left) = self.lines_left {
+            title.push_str(&format!(" - {lines_left} Lines Left"));
+        }
+        if let Some(seconds_left) = self.seconds_left {
+            title.push_str(&format!(
+                " - {}:{:02}",
+                seconds_left / 60,
+                seconds_left % 60
+            ));
+        }
+        if self.paused {
+            title.push_str(" - Paused");
+        }
+        let _ = self.canvas.window_mut().set_title(&title);
+    }
+
+    fn draw_border(&mut self, color: SDL2Color) {
+        self.canvas.set_draw_color(color);
+        for inset in 0..FLASH_THICKNESS {
+            let _ = self.canvas.draw_rect(Rect::new(
+                inset,
+                inset,
+                (self.width - 2 * inset) as u32,
+                (self.height - 2 * inset) as u32,
+            ));
+        }
+    }
+
+    fn draw_flash(&mut self) {
+        let Some((color, frames_left)) = self.flash else {
+            return;
+        };
+
+        self.draw_border(color);
+        self.flash = (frames_left > 1).then_some((color, frames_left - 1));
+    }
+
     fn draw_gridlines(&mut self) {
         self.canvas.set_draw_color(GRID_LINE_COLOR);
 
-        for x in 0..=tetris::PLAY_FIELD_WIDTH {
-            let x_pos = x as i32 * CELL_SIZE;
+        for x in 0..=self.columns {
+            let x_pos = x * CELL_SIZE;
             let _ = self.canvas.draw_line((x_pos, 0), (x_pos, self.height));
             let _ = self.canvas.draw_line(
                 (x_pos + CELL_SIZE - 1, 0),
@@ -91,8 +290,8 @@ impl SDL2RenderEngine {
             );
         }
 
-        for y in 0..=tetris::PLAY_FIELD_HEIGHT {
-            let y_pos = y as i32 * CELL_SIZE;
+        for y in 0..=self.rows {
+            let y_pos = y * CELL_SIZE;
             let _ = self.canvas.draw_line(
                 (0, y_pos + CELL_SIZE - 1),
                 (self.width, y_pos + CELL_SIZE - 1),
@@ -103,21 +302,133 @@ impl SDL2RenderEngine {
 
 impl RenderEngine for SDL2RenderEngine {
     fn render(&mut self, tetris: &Tetris) {
+        //games don't always start on level 0
+        if tetris.get_level() != self.level {
+            self.level = tetris.get_level();
+            self.update_title();
+        }
+        if tetris.is_paused() != self.paused {
+            self.paused = tetris.is_paused();
+            self.update_title();
+        }
+        //rounded up so it only shows 0:00 once the time is actually up
+        let countdown = tetris.get_countdown();
+        let seconds_left = countdown.map(|(time_left, _)| time_left.as_secs_f64().ceil() as u64);
+        let lines_left = tetris.get_lines_remaining();
+        if (seconds_left, lines_left) != (self.seconds_left, self.lines_left)
+            && tetris.get_state() == GameState::Playing
+        {
+            self.seconds_left = seconds_left;
+            self.lines_left = lines_left;
+            self.update_title();
+        }
+
         self.canvas.set_draw_color(BACKGROUND_COLOR);
         self.canvas.clear();
 
-        for x in 0..tetris::PLAY_FIELD_WIDTH {
-            for y in 0..tetris::PLAY_FIELD_HEIGHT {
-                if let Cell::Block(tetromino_type) = tetris.play_field[[x, y]] {
-                    self.draw_cell((x as i32, y as i32), get_tetronimo_colour(tetromino_type));
+        //there's no board to draw while the game is paused
+        if let Some(play_field) = tetris.get_play_field() {
+            for ((x, y), cell) in play_field.indexed_iter() {
+                if let Cell::Block(tetromino_type) = cell {
+                    self.draw_cell((x as i32, y as i32), get_tetronimo_colour(*tetromino_type));
                 }
             }
         }
 
-        self.draw_current_tetrimino(tetris.get_current_tetromino());
+        //the locked piece would give away part of the hidden board
+        if !self.paused {
+            self.draw_lock_flash();
+        }
+
+        if let Some((rows, progress)) = tetris.get_line_clear() {
+            self.draw_line_clear(rows, progress);
+        }
+
+        //between pieces and once the game is over there's no piece in play
+        if let Some(current_tetromino) = tetris.get_current_tetromino() {
+            if let Some(ghost_position) = tetris.ghost_position() {
+                self.draw_ghost_tetromino(current_tetromino, ghost_position);
+            }
+            self.draw_current_tetrimino(current_tetromino);
+        }
+
+        if let Some(held_tetromino) = tetris.get_held_tetromino() {
+            self.draw_sidebar_tetromino(held_tetromino, HOLD_ROW);
+        }
+
+        //only draw as many upcoming pieces as fit in the window
+        for (index, next_tetromino) in tetris.get_next_tetrominoes().enumerate() {
+            let row = NEXT_ROW + index as i32 * NEXT_SPACING;
+            if row + 1 >= self.rows {
+                break;
+            }
+            self.draw_sidebar_tetromino(next_tetromino, row);
+        }
+
+        //a single clear isn't a combo yet
+        self.draw_pips(tetris.get_combo().saturating_sub(1), COMBO_ROW, COMBO_COLOR);
+        self.draw_pips(
+            tetris.get_back_to_back(),
+            BACK_TO_BACK_ROW,
+            BACK_TO_BACK_COLOR,
+        );
+
+        if let Some((_, fraction_left)) = countdown {
+            self.draw_countdown(fraction_left);
+        }
 
         self.draw_gridlines();
+        match tetris.get_state() {
+            GameState::Playing => self.draw_flash(),
+            GameState::GameOver(_) => self.draw_border(GAME_OVER_COLOR),
+            GameState::Completed => self.draw_border(COMPLETED_COLOR),
+        }
 
         self.canvas.present();
     }
+
+    fn handle_event(&mut self, output_event: &OutputEvent) {
+        match output_event {
+            OutputEvent::LinesCleared {
+                kind: line_clear, ..
+            } => {
+                if line_clear.back_to_back {
+                    self.flash = Some((BACK_TO_BACK_COLOR, FLASH_FRAMES));
+                } else if line_clear.t_spin != TSpin::None {
+                    self.flash = Some((get_tetronimo_colour(TetrominoType::T), FLASH_FRAMES));
+                } else if line_clear.lines == 4 {
+                    self.flash = Some((get_tetronimo_colour(TetrominoType::Line), FLASH_FRAMES));
+                } else if line_clear.combo > 1 {
+                    self.flash = Some((COMBO_COLOR, FLASH_FRAMES));
+                }
+            }
+            OutputEvent::PieceLocked(tetromino) => {
+                self.lock_flash = Some((tetromino.clone(), LOCK_FLASH_FRAMES));
+            }
+            OutputEvent::ScoreChanged(score) => {
+                self.score = *score;
+                self.update_title();
+            }
+            OutputEvent::LevelUp(level) => {
+                self.level = *level;
+                self.update_title();
+            }
+            OutputEvent::TopOut { .. } => {
+                let title = format!(
+                    "Tetris - Game Over - Level {} - Score {}",
+                    self.level, self.score
+                );
+                let _ = self.canvas.window_mut().set_title(&title);
+            }
+            OutputEvent::Completed(statistics) => {
+                let title = format!(
+                    "Tetris - Complete - Time {:.2}s - Score {}",
+                    statistics.time().as_secs_f64(),
+                    statistics.score
+                );
+                let _ = self.canvas.window_mut().set_title(&title);
+            }
+            _ => {}
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use rand::Rng;
+use rand::{Rng, RngCore};
 
 const TETROMINO_SIZE: usize = 4;
 
@@ -9,52 +9,131 @@ pub type Position = (PositionIndex, PositionIndex);
 // array representing a Tetromino in a specific orientiation
 pub type TetrominoRotation = [(PositionIndex, PositionIndex); TETROMINO_SIZE];
 
-// Square piece (O) - only 1 rotation needed
-const SQUARE_PIECE_POSITIONS: [TetrominoRotation; 1] = [[(0, 0), (0, 1), (1, 0), (1, 1)]];
+const NUM_ROTATIONS: usize = 4;
 
-// Line piece (I) - 2 rotations needed
-const LINE_PIECE_POSITIONS: [TetrominoRotation; 2] = [
-    [(0, -1), (0, 0), (0, 1), (0, 2)], // Vertical
-    [(-1, 0), (0, 0), (1, 0), (2, 0)], // Horizontal
+// Rotation states follow the Super Rotation System: spawn (0), clockwise (R), 180 (2) and
+// counter-clockwise (L). Each table is defined relative to the piece's rotation centre.
+
+// Square piece (O) - rotates about the centre of the square so every state is identical
+const SQUARE_PIECE_POSITIONS: [TetrominoRotation; NUM_ROTATIONS] = [
+    [(0, 0), (1, 0), (0, 1), (1, 1)], // spawn
+    [(0, 0), (1, 0), (0, 1), (1, 1)], // right
+    [(0, 0), (1, 0), (0, 1), (1, 1)], // upside down
+    [(0, 0), (1, 0), (0, 1), (1, 1)], // left
+];
+
+// Line piece (I) - rotates about the centre of its 4x4 bounding box, (0.5, -0.5)
+const LINE_PIECE_POSITIONS: [TetrominoRotation; NUM_ROTATIONS] = [
+    [(-1, 0), (0, 0), (1, 0), (2, 0)],     // horizontal, upper row
+    [(1, 1), (1, 0), (1, -1), (1, -2)],    // vertical, right column
+    [(-1, -1), (0, -1), (1, -1), (2, -1)], // horizontal, lower row
+    [(0, 1), (0, 0), (0, -1), (0, -2)],    // vertical, left column
 ];
 
-// T piece - 4 rotations needed
-const T_PIECE_POSITIONS: [TetrominoRotation; 4] = [
+// T piece
+const T_PIECE_POSITIONS: [TetrominoRotation; NUM_ROTATIONS] = [
     [(-1, 0), (0, 0), (1, 0), (0, 1)],  // T upright
-    [(0, -1), (0, 0), (0, 1), (1, 0)],  // T right
-    [(-1, 0), (0, 0), (1, 0), (0, -1)], // T upside down
+    [(0, 1), (0, 0), (0, -1), (1, 0)],  // T right
+    [(1, 0), (0, 0), (-1, 0), (0, -1)], // T upside down
     [(0, -1), (0, 0), (0, 1), (-1, 0)], // T left
 ];
 
-// L piece - 4 rotations needed
-const L_PIECE_POSITIONS: [TetrominoRotation; 4] = [
-    [(0, -1), (0, 0), (0, 1), (1, 1)],   // L upright
-    [(-1, 0), (0, 0), (1, 0), (-1, 1)],  // L right
-    [(0, -1), (0, 0), (0, 1), (-1, -1)], // L upside down
-    [(-1, 0), (0, 0), (1, 0), (1, -1)],  // L left
+// L piece
+const L_PIECE_POSITIONS: [TetrominoRotation; NUM_ROTATIONS] = [
+    [(-1, 0), (0, 0), (1, 0), (1, 1)],   // L spawn
+    [(0, 1), (0, 0), (0, -1), (1, -1)],  // L right
+    [(1, 0), (0, 0), (-1, 0), (-1, -1)], // L upside down
+    [(0, -1), (0, 0), (0, 1), (-1, 1)],  // L left
 ];
 
-// J piece (reverse L) - 4 rotations needed
-const J_PIECE_POSITIONS: [TetrominoRotation; 4] = [
-    [(0, -1), (0, 0), (0, 1), (-1, 1)],  // J upright
-    [(-1, 0), (0, 0), (1, 0), (-1, -1)], // J right
-    [(0, -1), (0, 0), (0, 1), (1, -1)],  // J upside down
-    [(-1, 0), (0, 0), (1, 0), (1, 1)],   // J left
+// J piece (reverse L)
+const J_PIECE_POSITIONS: [TetrominoRotation; NUM_ROTATIONS] = [
+    [(-1, 1), (-1, 0), (0, 0), (1, 0)],  // J spawn
+    [(1, 1), (0, 1), (0, 0), (0, -1)],   // J right
+    [(1, -1), (1, 0), (0, 0), (-1, 0)],  // J upside down
+    [(-1, -1), (0, -1), (0, 0), (0, 1)], // J left
 ];
 
-// S piece - 2 rotations needed
-const S_PIECE_POSITIONS: [TetrominoRotation; 2] = [
-    [(1, 0), (0, 0), (0, 1), (-1, 1)],   // S horizontal
-    [(-1, -1), (-1, 0), (0, 0), (0, 1)], // S vertical
+// S piece
+const S_PIECE_POSITIONS: [TetrominoRotation; NUM_ROTATIONS] = [
+    [(-1, 0), (0, 0), (0, 1), (1, 1)],   // S spawn
+    [(0, 1), (0, 0), (1, 0), (1, -1)],   // S right
+    [(1, 0), (0, 0), (0, -1), (-1, -1)], // S upside down
+    [(0, -1), (0, 0), (-1, 0), (-1, 1)], // S left
 ];
 
-// Z piece - 2 rotations needed
-const Z_PIECE_POSITIONS: [TetrominoRotation; 2] = [
-    [(-1, 0), (0, 0), (0, 1), (1, 1)],   // Z horizontal
-    [(0, -1), (0, 0), (-1, 0), (-1, 1)], // Z vertical
+// Z piece
+const Z_PIECE_POSITIONS: [TetrominoRotation; NUM_ROTATIONS] = [
+    [(-1, 1), (0, 1), (0, 0), (1, 0)],   // Z spawn
+    [(1, 1), (1, 0), (0, 0), (0, -1)],   // Z right
+    [(1, -1), (0, -1), (0, 0), (-1, 0)], // Z upside down
+    [(-1, -1), (-1, 0), (0, 0), (0, 1)], // Z left
 ];
 
+const NUM_WALL_KICKS: usize = 5;
+
+// offsets tried in order when rotating clockwise out of each rotation state
+// (0->R, R->2, 2->L, L->0), the first one that fits is used
+const JLSTZ_WALL_KICKS: [[Position; NUM_WALL_KICKS]; NUM_ROTATIONS] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+];
+
+// counter-clockwise out of each rotation state (0->L, R->0, 2->R, L->2)
+const JLSTZ_CCW_WALL_KICKS: [[Position; NUM_WALL_KICKS]; NUM_ROTATIONS] = [
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+];
+
+const LINE_WALL_KICKS: [[Position; NUM_WALL_KICKS]; NUM_ROTATIONS] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+];
+
+const LINE_CCW_WALL_KICKS: [[Position; NUM_WALL_KICKS]; NUM_ROTATIONS] = [
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+];
+
+// SRS has no 180 degree rotations so these follow the widely used SRS+ table
+// (0->2, R->L, 2->0, L->R)
+const HALF_TURN_WALL_KICKS: [[Position; 6]; NUM_ROTATIONS] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+];
+
+// the square piece never needs to be kicked
+const SQUARE_WALL_KICKS: [Position; 1] = [(0, 0)];
+
 #[derive(Clone, Copy, Debug)]
+pub enum RotationDirection {
+    Clockwise,
+    CounterClockwise,
+    HalfTurn,
+}
+
+impl RotationDirection {
+    // how many clockwise quarter turns this rotation is equivalent to
+    fn quarter_turns(&self) -> usize {
+        match self {
+            RotationDirection::Clockwise => 1,
+            RotationDirection::HalfTurn => 2,
+            RotationDirection::CounterClockwise => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TetrominoType {
     Square,
     Line,
@@ -66,19 +145,21 @@ pub enum TetrominoType {
 }
 
 impl TetrominoType {
-    fn get_num_rotations(&self) -> usize {
-        match self {
-            TetrominoType::Square => SQUARE_PIECE_POSITIONS.len(),
-            TetrominoType::Line => LINE_PIECE_POSITIONS.len(),
-            TetrominoType::T => T_PIECE_POSITIONS.len(),
-            TetrominoType::L => L_PIECE_POSITIONS.len(),
-            TetrominoType::J => J_PIECE_POSITIONS.len(),
-            TetrominoType::S => S_PIECE_POSITIONS.len(),
-            TetrominoType::Z => Z_PIECE_POSITIONS.len(),
+    // offsets to try when rotating out of the given rotation state
+    fn get_wall_kicks(&self, rotation: usize, direction: RotationDirection) -> &'static [Position] {
+        match (self, direction) {
+            (TetrominoType::Square, _) => &SQUARE_WALL_KICKS,
+            (_, RotationDirection::HalfTurn) => &HALF_TURN_WALL_KICKS[rotation],
+            (TetrominoType::Line, RotationDirection::Clockwise) => &LINE_WALL_KICKS[rotation],
+            (TetrominoType::Line, RotationDirection::CounterClockwise) => {
+                &LINE_CCW_WALL_KICKS[rotation]
+            }
+            (_, RotationDirection::Clockwise) => &JLSTZ_WALL_KICKS[rotation],
+            (_, RotationDirection::CounterClockwise) => &JLSTZ_CCW_WALL_KICKS[rotation],
         }
     }
 
-    const ALL_TYPES: [TetrominoType; 7] = [
+    pub const ALL_TYPES: [TetrominoType; 7] = [
         TetrominoType::Square,
         TetrominoType::Line,
         TetrominoType::T,
@@ -88,8 +169,7 @@ impl TetrominoType {
         TetrominoType::Z,
     ];
 
-    pub fn random() -> Self {
-        let mut rng = rand::rng();
+    pub fn random(rng: &mut dyn RngCore) -> Self {
         Self::ALL_TYPES[rng.random_range(0..Self::ALL_TYPES.len())]
     }
 }
@@ -102,20 +182,27 @@ pub struct Tetromino {
 }
 
 impl Tetromino {
-    pub fn new(tetromino_type: TetrominoType) -> Self {
+    // a tetromino in its spawn orientation at the given position
+    pub fn new(tetromino_type: TetrominoType, position: Position) -> Self {
         Self {
             tetromino_type,
             rotation: 0,
-            position: (5, 20),
+            position,
         }
     }
 
-    pub fn random() -> Self {
-        Self::new(TetrominoType::random())
+    pub fn rotate(&mut self, direction: RotationDirection) {
+        self.rotation = (self.rotation + direction.quarter_turns()) % NUM_ROTATIONS;
+    }
+
+    // 0 is the spawn orientation, then each clockwise quarter turn after that
+    pub fn get_rotation(&self) -> usize {
+        self.rotation
     }
 
-    pub fn rotate(&mut self) {
-        self.rotation = (self.rotation + 1) % self.tetromino_type.get_num_rotations();
+    // the wall kicks to try, in order, if rotating from the current state collides
+    pub fn get_wall_kicks(&self, direction: RotationDirection) -> &'static [Position] {
+        self.tetromino_type.get_wall_kicks(self.rotation, direction)
     }
 
     pub fn get_positions(&self) -> &TetrominoRotation {